@@ -3,12 +3,27 @@
 /// needed to communicate with Dart.
 /// This should be used once, and only once,
 /// at the root of the `hub` crate.
+///
+/// This also installs a panic hook that forwards Rust panics to Dart as a
+/// dedicated `"RinfPanic"` signal, separate from the leveled log signal and
+/// not subject to the log-level filter, so they surface in Flutter instead of
+/// vanishing into the native console. Pass `panic_hook: false` to opt
+/// out, for apps that install their own hook:
+/// `write_interface!(panic_hook: false);`.
 macro_rules! write_interface {
   () => {
+    $crate::write_interface!(panic_hook: true);
+  };
+  ( panic_hook: $install_panic_hook:literal ) => {
     #[cfg(not(target_family = "wasm"))]
     #[unsafe(no_mangle)]
     extern "C" fn rinf_start_rust_logic_extern() {
       use rinf::debug_print;
+      $crate::install_rinf_logger();
+      $crate::send_defmt_template_table();
+      if $install_panic_hook {
+        $crate::install_panic_hook();
+      }
       let result = $crate::start_rust_logic(main);
       if let Err(err) = result {
         debug_print!("{}", err);
@@ -19,6 +34,11 @@ macro_rules! write_interface {
     #[wasm_bindgen::prelude::wasm_bindgen]
     pub fn rinf_start_rust_logic_extern() {
       use rinf::debug_print;
+      $crate::install_rinf_logger();
+      $crate::send_defmt_template_table();
+      if $install_panic_hook {
+        $crate::install_panic_hook();
+      }
       let result = $crate::start_rust_logic(main);
       if let Err(err) = result {
         debug_print!("{}", err);
@@ -32,14 +52,21 @@ macro_rules! write_interface {
 /// including web and mobile emulators.
 /// When debugging, using this macro is recommended over `println!`,
 /// as it seamlessly adapts to different environments.
-/// Note that this macro does nothing in release mode.
+///
+/// Emission is also gated on the runtime filter set by
+/// [`set_log_level`](crate::set_log_level), which defaults to allowing
+/// everything in debug builds. In release builds this is silenced by
+/// default unless `rinf` itself was built with the `"release-logging"`
+/// cargo feature, in which case a `Rinf.setLogLevel(...)` control signal
+/// from Dart can still raise the filter.
 #[macro_export]
 macro_rules! debug_print {
   ( $( $t:tt )* ) => {
     {
-      let rust_report = format!( $( $t )* );
-      #[cfg(debug_assertions)]
+      if (cfg!(debug_assertions) || $crate::RELEASE_LOGGING_ENABLED)
+        && $crate::log_level_enabled($crate::RinfLogLevel::Debug)
       {
+        let rust_report = format!( $( $t )* );
         let result = $crate::send_rust_signal(
           "RinfOut", // Special message ID for Rust output
           Vec::new(),
@@ -49,8 +76,502 @@ macro_rules! debug_print {
           println!("{}\n{}", err, rust_report);
         }
       }
-      #[cfg(not(debug_assertions))]
-      let _ = rust_report;
     }
   }
 }
+
+/// Sends a single leveled log record to Flutter, tagged with the
+/// module path, file, and line of the call site.
+/// Prefer the [`trace`](crate::trace), [`debug`](crate::debug),
+/// [`info`](crate::info), [`warn`](crate::warn), and
+/// [`error`](crate::error) macros over calling this one directly.
+#[macro_export]
+macro_rules! rinf_log {
+  ( $level:expr, $( $t:tt )* ) => {
+    {
+      if (cfg!(debug_assertions) || $crate::RELEASE_LOGGING_ENABLED)
+        && $crate::log_level_enabled($level)
+      {
+        let rust_report = format!( $( $t )* );
+        $crate::send_log_signal(
+          $level,
+          module_path!(),
+          file!(),
+          line!(),
+          &rust_report,
+        );
+      }
+    }
+  }
+}
+
+/// Logs a message to Flutter at the `Trace` level.
+#[macro_export]
+macro_rules! trace {
+  ( $( $t:tt )* ) => {
+    $crate::rinf_log!($crate::RinfLogLevel::Trace, $( $t )*)
+  }
+}
+
+/// Logs a message to Flutter at the `Debug` level.
+#[macro_export]
+macro_rules! debug {
+  ( $( $t:tt )* ) => {
+    $crate::rinf_log!($crate::RinfLogLevel::Debug, $( $t )*)
+  }
+}
+
+/// Logs a message to Flutter at the `Info` level.
+#[macro_export]
+macro_rules! info {
+  ( $( $t:tt )* ) => {
+    $crate::rinf_log!($crate::RinfLogLevel::Info, $( $t )*)
+  }
+}
+
+/// Logs a message to Flutter at the `Warn` level.
+#[macro_export]
+macro_rules! warn {
+  ( $( $t:tt )* ) => {
+    $crate::rinf_log!($crate::RinfLogLevel::Warn, $( $t )*)
+  }
+}
+
+/// Logs a message to Flutter at the `Error` level.
+#[macro_export]
+macro_rules! error {
+  ( $( $t:tt )* ) => {
+    $crate::rinf_log!($crate::RinfLogLevel::Error, $( $t )*)
+  }
+}
+
+/// Severity of a log record sent to Flutter.
+/// Mirrors [`log::Level`] so the two can convert losslessly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum RinfLogLevel {
+  Error = 1,
+  Warn = 2,
+  Info = 3,
+  Debug = 4,
+  Trace = 5,
+}
+
+impl From<log::Level> for RinfLogLevel {
+  fn from(level: log::Level) -> Self {
+    match level {
+      log::Level::Error => RinfLogLevel::Error,
+      log::Level::Warn => RinfLogLevel::Warn,
+      log::Level::Info => RinfLogLevel::Info,
+      log::Level::Debug => RinfLogLevel::Debug,
+      log::Level::Trace => RinfLogLevel::Trace,
+    }
+  }
+}
+
+impl std::fmt::Display for RinfLogLevel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      RinfLogLevel::Error => "ERROR",
+      RinfLogLevel::Warn => "WARN",
+      RinfLogLevel::Info => "INFO",
+      RinfLogLevel::Debug => "DEBUG",
+      RinfLogLevel::Trace => "TRACE",
+    };
+    f.write_str(name)
+  }
+}
+
+/// Packs a leveled log record and sends it to Flutter as a
+/// `"RinfLog"` signal, so the Dart side can filter and colorize
+/// by severity instead of receiving one undifferentiated stream
+/// of text the way `debug_print!` does.
+///
+/// Layout (newline-separated so it stays human-readable when a
+/// signal is dropped and falls back to `println!`):
+/// `{level}\n{target}\n{file}\n{line}\n{message}`.
+pub fn send_log_signal(
+  level: RinfLogLevel,
+  target: &str,
+  file: &str,
+  line: u32,
+  message: &str,
+) {
+  let rust_report = format!("{}\n{}\n{}\n{}\n{}", level, target, file, line, message);
+  let result = send_rust_signal(
+    "RinfLog", // Special message ID for leveled Rust logs
+    Vec::new(),
+    rust_report.clone().into_bytes(),
+  );
+  if let Err(err) = result {
+    println!("{}\n{}", err, rust_report);
+  }
+}
+
+/// Bridges the global [`log`] facade to Flutter, so that
+/// `log::info!`-style calls anywhere in the dependency tree are
+/// routed there too, not just calls to the [`trace`](crate::trace)
+/// family of macros.
+#[cfg(any(debug_assertions, feature = "release-logging"))]
+struct RinfLogger;
+
+#[cfg(any(debug_assertions, feature = "release-logging"))]
+impl log::Log for RinfLogger {
+  fn enabled(&self, metadata: &log::Metadata) -> bool {
+    log_level_enabled(metadata.level().into())
+  }
+
+  fn log(&self, record: &log::Record) {
+    if self.enabled(record.metadata()) {
+      send_log_signal(
+        record.level().into(),
+        record.target(),
+        record.file().unwrap_or(""),
+        record.line().unwrap_or(0),
+        &record.args().to_string(),
+      );
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+#[cfg(any(debug_assertions, feature = "release-logging"))]
+static RINF_LOGGER: RinfLogger = RinfLogger;
+
+/// Runtime log-level filter behind every logging path in this module,
+/// stored as a [`RinfLogLevel`] discriminant (`0` means nothing passes).
+/// Defaults to allowing everything in debug builds and, unless the
+/// `"release-logging"` cargo feature is enabled, is moot in release
+/// builds since the call sites that would check it are compiled out
+/// entirely. Set it from Dart through [`set_log_level`](crate::set_log_level),
+/// wired up to a `Rinf.setLogLevel(...)` control signal.
+#[cfg(debug_assertions)]
+const DEFAULT_LOG_LEVEL_FILTER: u8 = RinfLogLevel::Trace as u8;
+#[cfg(not(debug_assertions))]
+const DEFAULT_LOG_LEVEL_FILTER: u8 = 0; // Off, until Dart raises it with `Rinf.setLogLevel(...)`
+
+static LOG_LEVEL_FILTER: std::sync::atomic::AtomicU8 =
+  std::sync::atomic::AtomicU8::new(DEFAULT_LOG_LEVEL_FILTER);
+
+/// Returns `true` if a record at `level` currently passes the runtime
+/// filter last set by [`set_log_level`](crate::set_log_level).
+pub fn log_level_enabled(level: RinfLogLevel) -> bool {
+  level as u8 <= LOG_LEVEL_FILTER.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Whether `rinf` itself was built with the `"release-logging"` feature.
+/// `debug_print!`/`rinf_log!` check this instead of putting
+/// `cfg(feature = "release-logging")` directly in their (`#[macro_export]`)
+/// bodies: those macros expand inside the calling crate, so a `cfg`
+/// attribute there would check the caller's features, not `rinf`'s. This
+/// `const` is evaluated here, inside `rinf`'s own compilation, so it
+/// reflects `rinf`'s feature flags regardless of who calls the macro.
+pub const RELEASE_LOGGING_ENABLED: bool = cfg!(feature = "release-logging");
+
+/// Sets the runtime log-level filter used by every logging path in this
+/// module (`debug_print!`, `defmt_print!`, the [`trace`](crate::trace) family, and the
+/// installed [`log::Log`] bridge), including in release builds when the
+/// `"release-logging"` cargo feature is enabled. Pass `None` to silence
+/// Rinf logging entirely.
+///
+/// This is the Rust-side entry point for a `Rinf.setLogLevel(...)`
+/// control signal from Dart, letting testers dial verbosity up or down
+/// without a rebuild.
+pub fn set_log_level(level: Option<RinfLogLevel>) {
+  LOG_LEVEL_FILTER.store(
+    level.map(|level| level as u8).unwrap_or(0),
+    std::sync::atomic::Ordering::Relaxed,
+  );
+}
+
+/// Installs [`RinfLogger`] as the global logger for the [`log`] crate.
+/// `write_interface!` calls this automatically, so apps that only use
+/// `log::info!` and friends get Flutter-forwarded logs for free.
+/// Calling this more than once is harmless; only the first call wins.
+pub fn install_rinf_logger() {
+  #[cfg(any(debug_assertions, feature = "release-logging"))]
+  if log::set_logger(&RINF_LOGGER).is_ok() {
+    // The `log` crate's own static filter is left wide open; the actual
+    // filtering happens in `RinfLogger::enabled` against the runtime
+    // level set via `set_log_level`, so that Dart can adjust verbosity
+    // without needing `log::set_max_level` reconfigured too.
+    log::set_max_level(log::LevelFilter::Trace);
+  }
+}
+
+/// Re-exported so that `defmt_print!` can refer to `$crate::linkme`
+/// from a calling crate that doesn't depend on `linkme` itself.
+pub use linkme;
+
+/// Like [`debug_print`](crate::debug_print), but interns the format
+/// string at compile time and only sends its id plus the encoded
+/// arguments, with reconstruction happening on the Dart side.
+#[macro_export]
+macro_rules! defmt_print {
+  ( $fmt:literal $( , $arg:expr )* $(,)? ) => {{
+    #[$crate::linkme::distributed_slice($crate::LOG_TEMPLATES)]
+    #[linkme(crate = $crate::linkme)]
+    static TEMPLATE: $crate::LogTemplate = $crate::LogTemplate {
+      id: $crate::const_fnv1a_hash($fmt),
+      template: $fmt,
+    };
+    if (cfg!(debug_assertions) || $crate::RELEASE_LOGGING_ENABLED)
+      && $crate::log_level_enabled($crate::RinfLogLevel::Debug)
+    {
+      let mut encoded_args = Vec::new();
+      $( $crate::DefmtEncode::encode(&$arg, &mut encoded_args); )*
+      $crate::send_defmt_signal(TEMPLATE.id, encoded_args);
+    }
+  }}
+}
+
+/// One entry of the compile-time string table that backs
+/// [`defmt_print`](crate::defmt_print). Every call site registers one of
+/// these into [`LOG_TEMPLATES`] via `linkme`, so the full table can be
+/// collected and shipped to Dart without a build script.
+#[derive(Debug, Clone, Copy)]
+pub struct LogTemplate {
+  pub id: u32,
+  pub template: &'static str,
+}
+
+/// Link-time collected table of every format string ever passed to
+/// [`defmt_print`](crate::defmt_print) in this binary. Populated entirely
+/// by `linkme::distributed_slice` entries contributed from call sites;
+/// never pushed to directly.
+#[linkme::distributed_slice]
+pub static LOG_TEMPLATES: [LogTemplate] = [..];
+
+/// FNV-1a, computed in a `const fn` so that [`defmt_print`](crate::defmt_print)
+/// can assign each format string an id at compile time. The id depends only
+/// on the string's bytes, so the same format string gets the same id in
+/// every build; a hot reload that adds or edits format strings produces ids
+/// that a previously received table on the Dart side does not contain.
+pub const fn const_fnv1a_hash(s: &str) -> u32 {
+  let bytes = s.as_bytes();
+  let mut hash: u32 = 0x811c_9dc5;
+  let mut i = 0;
+  while i < bytes.len() {
+    hash ^= bytes[i] as u32;
+    hash = hash.wrapping_mul(0x0100_0193);
+    i += 1;
+  }
+  hash
+}
+
+/// Encodes a single `defmt_print!` argument into a self-describing,
+/// length-prefixed binary form: one type tag byte followed by the value's
+/// bytes (and, for variable-width values, a length prefix first). This is
+/// what lets the Dart decoder splice decoded args into a template's `{}`
+/// placeholders without any type information from Rust.
+pub trait DefmtEncode {
+  fn encode(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_defmt_encode_int {
+  ( $( $ty:ty => $tag:expr ),* $(,)? ) => {
+    $(
+      impl DefmtEncode for $ty {
+        fn encode(&self, out: &mut Vec<u8>) {
+          out.push($tag);
+          out.extend_from_slice(&self.to_le_bytes());
+        }
+      }
+    )*
+  }
+}
+
+impl_defmt_encode_int!(
+  u8 => 0,
+  u16 => 1,
+  u32 => 2,
+  u64 => 3,
+  i8 => 4,
+  i16 => 5,
+  i32 => 6,
+  i64 => 7,
+  f32 => 8,
+  f64 => 9,
+);
+
+impl DefmtEncode for bool {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.push(10);
+    out.push(*self as u8);
+  }
+}
+
+impl DefmtEncode for str {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.push(11);
+    out.extend_from_slice(&(self.len() as u32).to_le_bytes());
+    out.extend_from_slice(self.as_bytes());
+  }
+}
+
+impl DefmtEncode for &str {
+  fn encode(&self, out: &mut Vec<u8>) {
+    (*self).encode(out)
+  }
+}
+
+impl DefmtEncode for String {
+  fn encode(&self, out: &mut Vec<u8>) {
+    self.as_str().encode(out)
+  }
+}
+
+/// Sends one `defmt_print!` call as a `"RinfDefmtLog"` signal: the
+/// template id followed by the self-describing argument bytes produced by
+/// [`DefmtEncode`]. Falls back to `println!`-ing the raw bytes if the
+/// signal channel is unavailable, same as every other logging path here.
+pub fn send_defmt_signal(template_id: u32, encoded_args: Vec<u8>) {
+  let mut payload = Vec::with_capacity(4 + encoded_args.len());
+  payload.extend_from_slice(&template_id.to_le_bytes());
+  payload.extend_from_slice(&encoded_args);
+  let result = send_rust_signal(
+    "RinfDefmtLog", // Special message ID for interned, deferred-format logs
+    Vec::new(),
+    payload,
+  );
+  if let Err(err) = result {
+    println!("{}\nRinfDefmtLog template={} args={:?}", err, template_id, encoded_args);
+  }
+}
+
+/// Ships the full [`LOG_TEMPLATES`] table to Dart as a `"RinfLogTemplates"`
+/// signal, so the id-based log decoder has an id-to-template map before
+/// the first [`defmt_print`](crate::defmt_print) call arrives.
+/// `write_interface!` calls this once at startup. If this signal is never
+/// received (for example because of a hot reload that swapped the table
+/// without restarting Dart), the decoder still has the id and the
+/// self-describing argument bytes, so it can fall back to printing them
+/// as plain text instead of losing the log entirely.
+pub fn send_defmt_template_table() {
+  let mut payload = Vec::new();
+  for entry in LOG_TEMPLATES.iter() {
+    payload.extend_from_slice(&entry.id.to_le_bytes());
+    payload.extend_from_slice(&(entry.template.len() as u32).to_le_bytes());
+    payload.extend_from_slice(entry.template.as_bytes());
+  }
+  let result = send_rust_signal(
+    "RinfLogTemplates", // Special message ID for the defmt string table
+    Vec::new(),
+    payload,
+  );
+  if let Err(err) = result {
+    println!("{}\nfailed to send RinfLogTemplates", err);
+  }
+}
+
+/// Installs a [`std::panic::set_hook`] that forwards panics to Dart as a
+/// `"RinfPanic"` signal instead of letting them vanish into the native
+/// console (or nowhere at all on web), the same problem
+/// [`debug_print`](crate::debug_print) solves for ordinary output.
+/// `write_interface!` calls this automatically; pass
+/// `write_interface!(panic_hook: false);` to skip it if the app installs
+/// its own hook.
+///
+/// On WASM this routes through the same signal rather than relying on
+/// `console_error_panic_hook`, so the message reaches Dart even when no
+/// browser console is visible.
+pub fn install_panic_hook() {
+  #[cfg(target_family = "wasm")]
+  {
+    std::panic::set_hook(Box::new(|panic_info| {
+      send_panic_signal(&panic_info.to_string());
+    }));
+  }
+
+  #[cfg(not(target_family = "wasm"))]
+  {
+    std::panic::set_hook(Box::new(|panic_info| {
+      let backtrace = std::backtrace::Backtrace::force_capture();
+      send_panic_signal(&format!("{}\n\nbacktrace:\n{}", panic_info, backtrace));
+    }));
+  }
+}
+
+/// Sends a captured panic message to Dart as a `"RinfPanic"` signal,
+/// separate from `"RinfLog"` so the app can always surface it (in a
+/// dialog or a crash reporter) regardless of the current log level.
+fn send_panic_signal(message: &str) {
+  let result = send_rust_signal(
+    "RinfPanic", // Special message ID for forwarded Rust panics
+    Vec::new(),
+    message.as_bytes().to_vec(),
+  );
+  if let Err(err) = result {
+    println!("{}\n{}", err, message);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fnv1a_hash_is_stable_and_sensitive_to_input() {
+    assert_eq!(const_fnv1a_hash(""), 0x811c_9dc5);
+    assert_eq!(const_fnv1a_hash("hello"), const_fnv1a_hash("hello"));
+    assert_ne!(const_fnv1a_hash("hello"), const_fnv1a_hash("world"));
+  }
+
+  #[test]
+  fn log_level_filter_defaults_to_everything_in_debug_builds() {
+    #[cfg(debug_assertions)]
+    assert!(log_level_enabled(RinfLogLevel::Trace));
+  }
+
+  #[test]
+  fn set_log_level_raises_and_lowers_the_filter() {
+    set_log_level(Some(RinfLogLevel::Warn));
+    assert!(log_level_enabled(RinfLogLevel::Error));
+    assert!(log_level_enabled(RinfLogLevel::Warn));
+    assert!(!log_level_enabled(RinfLogLevel::Info));
+
+    set_log_level(None);
+    assert!(!log_level_enabled(RinfLogLevel::Error));
+
+    set_log_level(Some(RinfLogLevel::Trace));
+    assert!(log_level_enabled(RinfLogLevel::Trace));
+  }
+
+  #[test]
+  fn defmt_encode_ints_write_tag_then_le_bytes() {
+    let mut out = Vec::new();
+    42u32.encode(&mut out);
+    assert_eq!(out, {
+      let mut expected = vec![2u8];
+      expected.extend_from_slice(&42u32.to_le_bytes());
+      expected
+    });
+  }
+
+  #[test]
+  fn defmt_encode_bool_writes_tag_then_byte() {
+    let mut out = Vec::new();
+    true.encode(&mut out);
+    assert_eq!(out, vec![10, 1]);
+  }
+
+  #[test]
+  fn defmt_encode_str_writes_tag_then_length_prefixed_bytes() {
+    let mut out = Vec::new();
+    "hi".encode(&mut out);
+    let mut expected = vec![11u8];
+    expected.extend_from_slice(&2u32.to_le_bytes());
+    expected.extend_from_slice(b"hi");
+    assert_eq!(out, expected);
+  }
+
+  #[test]
+  fn defmt_encode_string_matches_str() {
+    let mut from_string = Vec::new();
+    String::from("hi").encode(&mut from_string);
+    let mut from_str = Vec::new();
+    "hi".encode(&mut from_str);
+    assert_eq!(from_string, from_str);
+  }
+}